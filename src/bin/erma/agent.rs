@@ -2,43 +2,191 @@ use core::future::Ready;
 
 use std::future::ready;
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use tracing::{span, warn, Level};
 
 use domain::base::iana::{Class, Rcode};
-use domain::base::message_builder::{AdditionalBuilder, AnswerBuilder};
+use domain::base::message_builder::{AdditionalBuilder, AnswerBuilder, AuthorityBuilder};
 use domain::base::name::{Label, ToLabelIter};
+use domain::base::rdata::ComposeRecordData;
 use domain::base::wire::Composer;
-use domain::base::{CharStr, NameBuilder, ParsedName, RelativeName, Rtype, StreamTarget, Ttl};
+use domain::base::{
+    CharStr, Name, NameBuilder, ParsedName, RelativeName, Rtype, StreamTarget, Ttl,
+};
 use domain::net::server::message::Request;
 use domain::net::server::service::{CallResult, Service, ServiceError, Transaction};
 use domain::net::server::util::mk_builder_for_target;
 use domain::rdata::rfc1035::TxtBuilder;
+use domain::rdata::rfc4034::Rrsig;
+
+use crate::signing::ZoneSigningKey;
+use crate::sink::ReportSink;
+use crate::zone::{LookupResult, Zone};
+
+/// Returns the wire-form RDATA bytes of `rdata`, as needed to feed the RRSIG
+/// signing input (RFC 4034 section 3.1.8.1).
+fn compose_rdata<D: ComposeRecordData>(rdata: &D) -> Vec<u8> {
+    let mut buf = Vec::new();
+    rdata
+        .compose_rdata(&mut buf)
+        .expect("composing into a Vec cannot fail");
+    buf
+}
+
+/// Default validity period for RRSIGs minted by this agent: 7 days.
+/// Overridable via [`AgentService::with_signature_validity_secs`].
+pub const DEFAULT_SIGNATURE_VALIDITY_SECS: u32 = 7 * 24 * 60 * 60;
+
+/// The largest validity period `--signature-validity-secs`/
+/// `signature_validity_secs` is allowed to configure: roughly 10 years.
+///
+/// Combined with `saturating_add` in [`AgentService::sign_rrsig`], this
+/// keeps `now + validity` well clear of `u32::MAX`, so a too-large,
+/// operator-supplied validity clamps to a (very long but still sane) fixed
+/// expiration instead of overflowing into a pre-expired timestamp.
+pub const MAX_SIGNATURE_VALIDITY_SECS: u32 = 10 * 365 * 24 * 60 * 60;
+
+//----------- QnameError --------------------------------------------------------
+
+/// An error encountered while parsing a report QNAME per RFC 9567.
+#[derive(Debug)]
+enum QnameError {
+    /// The QNAME didn't follow the `_er.<qtype>.<qname>.<edns error
+    /// code>._er.<agent domain>` structure required by RFC 9567.
+    Malformed(String),
+
+    /// The QNAME was well-formed, but the `<agent domain>` suffix doesn't
+    /// match any domain this instance is authoritative for.
+    WrongAgentDomain(String),
+}
+
+impl QnameError {
+    fn malformed(msg: impl Into<String>) -> Self {
+        QnameError::Malformed(msg.into())
+    }
+}
+
+impl std::fmt::Display for QnameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QnameError::Malformed(msg) => write!(f, "{msg}"),
+            QnameError::WrongAgentDomain(domain) => {
+                write!(
+                    f,
+                    "query targets agent domain '{domain}', which this instance does not serve"
+                )
+            }
+        }
+    }
+}
 
 //----------- AgentService ---------------------------------------------------
 
 /// A `Service` impl that acts as an [RFC 9567] error reporting agent.
 ///
 /// [RFC 9567]: https://datatracker.ietf.org/doc/rfc9567/
-pub struct AgentService<F>
-where
-    F: Fn(u16, u16, RelativeName<Vec<u8>>),
-{
-    // agent_domain: RelativeName?
-    /// A user supplied callback function that will handle received reports.
+pub struct AgentService {
+    /// The sinks received reports are fanned out to, in order.
+    sinks: Vec<Box<dyn ReportSink>>,
+
+    /// The zone signing key for this agent domain, if DNSSEC signing is
+    /// enabled.
     ///
-    /// Will be passed the reported QTYPE, EDNS error code and QNAME as
-    /// arguments.
-    callback: F,
+    /// When set, responses to DO=1 queries carry an RRSIG over the answer
+    /// RRset, and apex queries for DNSKEY are answered with the public key.
+    signing_key: Option<ZoneSigningKey>,
+
+    /// The apex name of the agent domain, used to answer DNSKEY queries.
+    ///
+    /// Only meaningful when `signing_key` is `Some`.
+    apex: Option<Name<Vec<u8>>>,
+
+    /// The agent domain(s) this instance is authoritative for.
+    ///
+    /// A report query is only accepted if its QNAME's `<agent domain>`
+    /// suffix (after the trailing `_er` label) matches one of these as a
+    /// DNS name (not a raw string, so a trailing dot or differing
+    /// capitalization in config doesn't cause false REFUSEDs). Empty means
+    /// "accept any domain", for backwards compatibility with deployments
+    /// that don't configure this.
+    agent_domains: Vec<Name<Vec<u8>>>,
+
+    /// The authoritative zone for the agent domain, if one was loaded from
+    /// a zonefile.
+    ///
+    /// When set, ordinary (non-report) questions are answered from it
+    /// instead of falling through to FORMERR, making this instance a
+    /// complete authoritative server for the agent domain.
+    zone: Option<Zone>,
+
+    /// How long RRSIGs minted by this agent stay valid for, in seconds.
+    signature_validity_secs: u32,
 }
 
-impl<F> AgentService<F>
-where
-    F: Fn(u16, u16, RelativeName<Vec<u8>>),
-{
-    /// Creates a new instance of this service.
-    pub fn new(callback: F) -> Self {
-        Self { callback }
+impl AgentService {
+    /// Creates a new instance of this service, fanning out received reports
+    /// to `sinks`.
+    pub fn new(sinks: Vec<Box<dyn ReportSink>>) -> Self {
+        Self {
+            sinks,
+            signing_key: None,
+            apex: None,
+            agent_domains: Vec::new(),
+            zone: None,
+            signature_validity_secs: DEFAULT_SIGNATURE_VALIDITY_SECS,
+        }
+    }
+
+    /// Creates a new instance of this service that DNSSEC-signs its
+    /// answers with `signing_key`, serving its DNSKEY RRset at `apex`.
+    pub fn with_signing_key(
+        sinks: Vec<Box<dyn ReportSink>>,
+        signing_key: ZoneSigningKey,
+        apex: Name<Vec<u8>>,
+    ) -> Self {
+        Self {
+            sinks,
+            signing_key: Some(signing_key),
+            apex: Some(apex),
+            agent_domains: Vec::new(),
+            zone: None,
+            signature_validity_secs: DEFAULT_SIGNATURE_VALIDITY_SECS,
+        }
+    }
+
+    /// Restricts report queries to the given agent domain(s); queries for
+    /// any other domain are answered with REFUSED. Defaults to accepting
+    /// any domain when never called.
+    ///
+    /// Domains that fail to parse as a DNS name are logged and skipped.
+    pub fn with_agent_domains(mut self, agent_domains: Vec<String>) -> Self {
+        self.agent_domains = agent_domains
+            .iter()
+            .filter_map(|domain| match Name::<Vec<u8>>::from_str(domain) {
+                Ok(name) => Some(name),
+                Err(err) => {
+                    warn!("Ignoring invalid agent domain {domain:?}: {err}");
+                    None
+                }
+            })
+            .collect();
+        self
+    }
+
+    /// Serves `zone` as a complete authoritative zone: ordinary questions
+    /// (SOA, NS, and anything else the zonefile declares) are answered
+    /// from it, with report queries still handled by the `_er` path.
+    pub fn with_zone(mut self, zone: Zone) -> Self {
+        self.zone = Some(zone);
+        self
+    }
+
+    /// Overrides how long RRSIGs minted by this agent stay valid for.
+    /// Defaults to [`DEFAULT_SIGNATURE_VALIDITY_SECS`] when never called.
+    pub fn with_signature_validity_secs(mut self, signature_validity_secs: u32) -> Self {
+        self.signature_validity_secs = signature_validity_secs;
+        self
     }
 
     /// Process an agent request per RFC 9567, if valid.
@@ -70,9 +218,6 @@ where
             // check it but one can imagine that it only makes sense for it to be
             // IN.
             //
-            // TODO: Should we enforce that the <our agent domain> part of the
-            // QNAME matches what we think our agent domain is?
-            //
             // See:
             // https://www.rfc-editor.org/rfc/rfc9567#name-constructing-the-report-que
             let qname = question.qname();
@@ -82,21 +227,52 @@ where
             let span = span!(Level::INFO, "Processing", %qname, %qtype);
             let _enter = span.enter();
 
-            if qtype == Rtype::TXT {
-                if num_labels >= 6 {
-                    match self.parse_qname(qname) {
-                        Err(err) => warn!("QNAME parsing error: {err}"),
+            let dnssec_ok = request
+                .message()
+                .opt()
+                .map(|opt| opt.dnssec_ok())
+                .unwrap_or(false);
 
-                        Ok((rep_qtype, edns_err_code, rep_qname)) => {
-                            (self.callback)(rep_qtype, edns_err_code, rep_qname);
-                            response = Some(self.mk_success_response(request, qname));
+            let is_apex_query = self
+                .apex
+                .as_ref()
+                .and_then(|apex| qname.to_name::<Vec<u8>>().ok().map(|n| n == *apex))
+                .unwrap_or(false);
+
+            // Report queries have a fixed shape (RFC 9567 section 6.1):
+            // `_er.<qtype>.<qname labels...>.<edns error code>._er.<agent
+            // domain>`, at least 6 labels, leading with `_er`. Anything
+            // else is an ordinary question about the agent domain, which
+            // we answer from the loaded zone if we have one.
+            let is_report_query = qtype == Rtype::TXT
+                && num_labels >= 6
+                && qname.iter_labels().next().is_some_and(|l| l == "_er");
+
+            if is_report_query {
+                match self.parse_qname(qname) {
+                    Err(err @ QnameError::WrongAgentDomain(_)) => {
+                        warn!("QNAME parsing error: {err}");
+                        response = Some(self.mk_err_response(request, Rcode::REFUSED));
+                    }
+
+                    Err(err) => warn!("QNAME parsing error: {err}"),
+
+                    Ok((rep_qtype, edns_err_code, rep_qname)) => {
+                        let client_addr = request.client_addr();
+                        for sink in &self.sinks {
+                            sink.report(rep_qtype, edns_err_code, &rep_qname, client_addr);
                         }
+                        response = Some(self.mk_success_response(request, qname, dnssec_ok));
                     }
-                } else {
-                    warn!("Insufficient labels in QNAME");
                 }
-            } else {
+            } else if self.zone.is_some() {
+                response = Some(self.mk_zone_response(request, qname, qtype, dnssec_ok));
+            } else if qtype == Rtype::DNSKEY && is_apex_query {
+                response = Some(self.mk_dnskey_response(request, qname));
+            } else if qtype != Rtype::TXT {
                 warn!("Invalid QTYPE: {qtype}");
+            } else {
+                warn!("QNAME is not a report query (fewer than 6 labels or no leading _er label)");
             }
         } else {
             warn!("QDCOUNT != 1");
@@ -106,31 +282,35 @@ where
             response = Some(self.mk_err_response(request, Rcode::FORMERR));
         }
 
-        response.unwrap().additional()
+        response.unwrap()
     }
 
     /// Parse a QNAME per the RFC 9567 agent query specification.
     ///
     /// Returns Ok((report qtype, report edns error code, report qname)) on
-    /// success, Err(String) otherwise.
+    /// success, Err([`QnameError`]) otherwise.
     fn parse_qname(
         &self,
         qname: &ParsedName<&[u8]>,
-    ) -> Result<(u16, u16, RelativeName<Vec<u8>>), String> {
+    ) -> Result<(u16, u16, RelativeName<Vec<u8>>), QnameError> {
         let mut iter = qname.iter_labels();
-        let _er = iter.next().ok_or("Missing _er label.".to_string())?;
-        let rep_qtype = iter.next().ok_or("Missing QTYPE label.".to_string())?;
+        let _er = iter
+            .next()
+            .ok_or(QnameError::malformed("Missing _er label."))?;
+        let rep_qtype = iter
+            .next()
+            .ok_or(QnameError::malformed("Missing QTYPE label."))?;
         let mut rep_qname = NameBuilder::new_vec();
         let mut second_last_label = Option::<&Label>::None;
         let mut last_label = None;
         loop {
             let label = iter
                 .next()
-                .ok_or("Missing QNAME or _er label.".to_string())?;
+                .ok_or(QnameError::malformed("Missing QNAME or _er label."))?;
             if let Some(label) = second_last_label {
                 rep_qname
                     .append_label(label.as_slice())
-                    .map_err(|err| format!("Invalid QNAME label: {err}"))?;
+                    .map_err(|err| QnameError::malformed(format!("Invalid QNAME label: {err}")))?;
             }
             if label == "_er" {
                 break;
@@ -140,37 +320,327 @@ where
             }
         }
         let rep_qname = rep_qname.finish();
-        let edns_err_code = last_label.ok_or("Missing EDNS error code label.".to_string())?;
+        let edns_err_code =
+            last_label.ok_or(QnameError::malformed("Missing EDNS error code label."))?;
 
         let rep_qtype = u16::from_str(&rep_qtype.to_string())
-            .map_err(|err| format!("Invalid QTYPE label: {err}"))?;
+            .map_err(|err| QnameError::malformed(format!("Invalid QTYPE label: {err}")))?;
+
+        let edns_err_code = u16::from_str(&edns_err_code.to_string()).map_err(|err| {
+            QnameError::malformed(format!("Invalid EDNS error code label: {err}"))
+        })?;
 
-        let edns_err_code = u16::from_str(&edns_err_code.to_string())
-            .map_err(|err| format!("Invalid EDNS error code label: {err}"))?;
+        // The remaining labels are the agent domain the query was addressed
+        // to; an empty `self.agent_domains` means "accept any domain"
+        // (e.g. no config/`--agent-domain` was given). Parsed as a `Name`
+        // rather than compared as a raw string, so a trailing dot or
+        // differing capitalization in config doesn't cause a false
+        // REFUSED.
+        let remaining_domain: Vec<String> = iter
+            .map(|label| label.to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let remaining_domain = remaining_domain.join(".");
+        let remaining_name = Name::<Vec<u8>>::from_str(&remaining_domain)
+            .map_err(|err| QnameError::malformed(format!("Invalid agent domain: {err}")))?;
+
+        if !self.agent_domains.is_empty()
+            && !self
+                .agent_domains
+                .iter()
+                .any(|domain| domain == &remaining_name)
+        {
+            return Err(QnameError::WrongAgentDomain(remaining_domain));
+        }
 
         Ok((rep_qtype, edns_err_code, rep_qname))
     }
 
     /// Construct an RFC 9567 TXT DNS answer response.
+    ///
+    /// If a zone signing key is configured and `dnssec_ok` is set (the
+    /// querier set the EDNS DO bit), an RRSIG covering the TXT RRset is
+    /// appended as well.
     fn mk_success_response<Target: Composer + Default>(
         &self,
         request: &Request<Vec<u8>>,
         qname: &ParsedName<&[u8]>,
-    ) -> AnswerBuilder<StreamTarget<Target>> {
+        dnssec_ok: bool,
+    ) -> AdditionalBuilder<StreamTarget<Target>> {
         let builder = mk_builder_for_target();
         let mut answer = builder
             .start_answer(request.message(), Rcode::NOERROR)
             .unwrap();
+        let ttl = Ttl::from_days(1);
         let mut txt_builder = TxtBuilder::<Vec<u8>>::new();
         let txt = {
             let cs = CharStr::<Vec<u8>>::from_str("Report received").unwrap();
             txt_builder.append_charstr(&cs).unwrap();
             txt_builder.finish().unwrap()
         };
-        answer
-            .push((qname, Class::IN, Ttl::from_days(1), txt))
+        answer.push((qname, Class::IN, ttl, txt.clone())).unwrap();
+
+        if dnssec_ok {
+            if let Some(signing_key) = &self.signing_key {
+                if let Ok(owner) = qname.to_name::<Vec<u8>>() {
+                    self.push_rrsig(
+                        &mut answer,
+                        signing_key,
+                        &owner,
+                        Rtype::TXT,
+                        ttl,
+                        &[compose_rdata(&txt)],
+                    );
+                }
+            }
+        }
+
+        answer.additional()
+    }
+
+    /// Signs a single RRset with `signing_key`, returning the resulting
+    /// RRSIG record, or `None` (after logging) if signing failed.
+    fn sign_rrsig(
+        &self,
+        signing_key: &ZoneSigningKey,
+        owner: &Name<Vec<u8>>,
+        rtype: Rtype,
+        ttl: Ttl,
+        rrset: &[Vec<u8>],
+    ) -> Option<Rrsig<Vec<u8>, Name<Vec<u8>>>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock is after the Unix epoch")
+            .as_secs() as u32;
+        let inception = now;
+        // `saturating_add`, not `wrapping_add`: `AgentService` itself puts no
+        // upper bound on `signature_validity_secs` (only the CLI/config path
+        // clamps it to `MAX_SIGNATURE_VALIDITY_SECS`), so a large enough
+        // value could otherwise wrap `now + validity` past `u32::MAX` and
+        // land on a timestamp in the past, minting RRSIGs that are
+        // pre-expired the moment they're issued.
+        let expiration = now.saturating_add(self.signature_validity_secs);
+
+        match signing_key.sign_rrset(owner, Class::IN, rtype, ttl, inception, expiration, rrset) {
+            Ok(rrsig) => Some(rrsig),
+            Err(err) => {
+                warn!("Failed to sign {rtype} RRset: {err}");
+                None
+            }
+        }
+    }
+
+    /// Appends an RRSIG record over a single RRset to `answer`, signed with
+    /// `signing_key`.
+    fn push_rrsig<Target: Composer + Default>(
+        &self,
+        answer: &mut AnswerBuilder<StreamTarget<Target>>,
+        signing_key: &ZoneSigningKey,
+        owner: &Name<Vec<u8>>,
+        rtype: Rtype,
+        ttl: Ttl,
+        rrset: &[Vec<u8>],
+    ) {
+        if let Some(rrsig) = self.sign_rrsig(signing_key, owner, rtype, ttl, rrset) {
+            if let Err(err) = answer.push((owner, Class::IN, ttl, rrsig)) {
+                warn!("Failed to append RRSIG to response: {err}");
+            }
+        }
+    }
+
+    /// Appends an RRSIG record over a single RRset to the authority
+    /// section, signed with `signing_key`. Used for the SOA accompanying
+    /// synthesized NODATA/NXDOMAIN zone responses.
+    fn push_rrsig_authority<Target: Composer + Default>(
+        &self,
+        authority: &mut AuthorityBuilder<StreamTarget<Target>>,
+        signing_key: &ZoneSigningKey,
+        owner: &Name<Vec<u8>>,
+        rtype: Rtype,
+        ttl: Ttl,
+        rrset: &[Vec<u8>],
+    ) {
+        if let Some(rrsig) = self.sign_rrsig(signing_key, owner, rtype, ttl, rrset) {
+            if let Err(err) = authority.push((owner, Class::IN, ttl, rrsig)) {
+                warn!("Failed to append RRSIG to authority section: {err}");
+            }
+        }
+    }
+
+    /// Construct a response to a DNSKEY query for the agent domain apex,
+    /// always signed since a DNSKEY is only meaningful under DNSSEC.
+    fn mk_dnskey_response<Target: Composer + Default>(
+        &self,
+        request: &Request<Vec<u8>>,
+        qname: &ParsedName<&[u8]>,
+    ) -> AdditionalBuilder<StreamTarget<Target>> {
+        let builder = mk_builder_for_target();
+        let mut answer = builder
+            .start_answer(request.message(), Rcode::NOERROR)
             .unwrap();
+
+        // Only reachable when `self.signing_key` is `Some`, see
+        // `is_apex_query`/the zone-apex-DNSKEY check in `process_request`.
+        let Some(signing_key) = &self.signing_key else {
+            return answer.additional();
+        };
+        let ttl = Ttl::from_days(1);
+        let dnskey = signing_key.dnskey().clone();
         answer
+            .push((qname, Class::IN, ttl, dnskey.clone()))
+            .unwrap();
+
+        if let Ok(owner) = qname.to_name::<Vec<u8>>() {
+            self.push_rrsig(
+                &mut answer,
+                signing_key,
+                &owner,
+                Rtype::DNSKEY,
+                ttl,
+                &[compose_rdata(&dnskey)],
+            );
+        }
+
+        answer.additional()
+    }
+
+    /// Construct a response to an ordinary (non-report) question about the
+    /// agent domain by looking it up in the loaded zone.
+    ///
+    /// DNSKEY queries for the apex are still served by [`Self::mk_dnskey_response`]
+    /// when a signing key is configured, since the zone itself doesn't carry
+    /// one. Everything else is answered straight from the zone, with
+    /// NODATA/NXDOMAIN synthesized with the AA bit set and the apex SOA in
+    /// the authority section. If a zone signing key is configured and
+    /// `dnssec_ok` is set, the answer (or authority-section SOA, for
+    /// negative responses) is signed exactly like [`Self::mk_success_response`]
+    /// signs the report ack — a zone can't be partially signed without every
+    /// unsigned answer looking bogus to a validating resolver.
+    fn mk_zone_response<Target: Composer + Default>(
+        &self,
+        request: &Request<Vec<u8>>,
+        qname: &ParsedName<&[u8]>,
+        qtype: Rtype,
+        dnssec_ok: bool,
+    ) -> AdditionalBuilder<StreamTarget<Target>> {
+        let zone = self
+            .zone
+            .as_ref()
+            .expect("only called when a zone is loaded");
+
+        if qtype == Rtype::DNSKEY && self.signing_key.is_some() {
+            if let Ok(owner) = qname.to_name::<Vec<u8>>() {
+                if owner == *zone.apex() {
+                    return self.mk_dnskey_response(request, qname);
+                }
+            }
+        }
+
+        let Ok(owner) = qname.to_name::<Vec<u8>>() else {
+            return self.mk_err_response(request, Rcode::FORMERR);
+        };
+
+        if !owner.ends_with(zone.apex()) {
+            return self.mk_err_response(request, Rcode::REFUSED);
+        }
+
+        match zone.lookup(&owner, qtype) {
+            LookupResult::Found(rrset) => {
+                let builder = mk_builder_for_target();
+                let mut answer = builder
+                    .start_answer(request.message(), Rcode::NOERROR)
+                    .unwrap();
+                answer.header_mut().set_aa(true);
+                for record in &rrset {
+                    if let Err(err) =
+                        answer.push((&owner, Class::IN, record.ttl, record.data.clone()))
+                    {
+                        warn!("Failed to append zone record to response: {err}");
+                    }
+                }
+
+                if dnssec_ok {
+                    if let Some(signing_key) = &self.signing_key {
+                        self.sign_zone_rrset(&mut answer, signing_key, &owner, &rrset);
+                    }
+                }
+
+                answer.additional()
+            }
+            LookupResult::NoData => {
+                self.mk_negative_zone_response(request, zone, Rcode::NOERROR, dnssec_ok)
+            }
+            LookupResult::NxDomain => {
+                self.mk_negative_zone_response(request, zone, Rcode::NXDOMAIN, dnssec_ok)
+            }
+        }
+    }
+
+    /// Signs `rrset` (as returned for a single owner/qtype by [`Zone::lookup`])
+    /// and appends the RRSIG to `answer`.
+    ///
+    /// Only signs when every record shares the same RDATA type: a `qtype:
+    /// ANY` lookup can return a mix, and RFC 4034 RRSIGs cover exactly one
+    /// type at a time, so such a response is left unsigned rather than
+    /// guessing which subset to cover.
+    fn sign_zone_rrset<Target: Composer + Default>(
+        &self,
+        answer: &mut AnswerBuilder<StreamTarget<Target>>,
+        signing_key: &ZoneSigningKey,
+        owner: &Name<Vec<u8>>,
+        rrset: &[&crate::zone::ZoneRecord],
+    ) {
+        let Some(first) = rrset.first() else {
+            return;
+        };
+        let rtype = first.data.rtype();
+        if !rrset.iter().all(|record| record.data.rtype() == rtype) {
+            return;
+        }
+
+        let ttl = first.ttl;
+        let wire_rrset: Vec<Vec<u8>> = rrset
+            .iter()
+            .map(|record| compose_rdata(&record.data))
+            .collect();
+        self.push_rrsig(answer, signing_key, owner, rtype, ttl, &wire_rrset);
+    }
+
+    /// Construct a NODATA (`rcode` NOERROR) or NXDOMAIN response carrying
+    /// the zone's apex SOA in the authority section, per the usual negative
+    /// caching convention (RFC 2308). Signs that SOA when `dnssec_ok` and a
+    /// signing key are both set.
+    fn mk_negative_zone_response<Target: Composer + Default>(
+        &self,
+        request: &Request<Vec<u8>>,
+        zone: &Zone,
+        rcode: Rcode,
+        dnssec_ok: bool,
+    ) -> AdditionalBuilder<StreamTarget<Target>> {
+        let builder = mk_builder_for_target();
+        let mut answer = builder.start_answer(request.message(), rcode).unwrap();
+        answer.header_mut().set_aa(true);
+
+        let mut authority = answer.authority();
+        let soa = zone.soa();
+        if let Err(err) = authority.push((zone.apex(), Class::IN, soa.ttl, soa.data.clone())) {
+            warn!("Failed to append SOA to authority section: {err}");
+        }
+
+        if dnssec_ok {
+            if let Some(signing_key) = &self.signing_key {
+                self.push_rrsig_authority(
+                    &mut authority,
+                    signing_key,
+                    zone.apex(),
+                    Rtype::SOA,
+                    soa.ttl,
+                    &[compose_rdata(&soa.data)],
+                );
+            }
+        }
+
+        authority.additional()
     }
 
     /// Construct a DNS error response.
@@ -178,18 +648,18 @@ where
         &self,
         request: &Request<Vec<u8>>,
         rcode: Rcode,
-    ) -> AnswerBuilder<StreamTarget<Target>> {
+    ) -> AdditionalBuilder<StreamTarget<Target>> {
         let builder = mk_builder_for_target();
-        builder.start_answer(request.message(), rcode).unwrap()
+        builder
+            .start_answer(request.message(), rcode)
+            .unwrap()
+            .additional()
     }
 }
 
 //--- Service
 
-impl<F> Service<Vec<u8>> for AgentService<F>
-where
-    F: Fn(u16, u16, RelativeName<Vec<u8>>),
-{
+impl Service<Vec<u8>> for AgentService {
     type Target = Vec<u8>;
     type Future = Ready<Result<CallResult<Self::Target>, ServiceError>>;
 
@@ -203,3 +673,93 @@ where
         Ok(txn)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use domain::base::iana::Class;
+    use domain::base::{Message, MessageBuilder};
+
+    use super::*;
+
+    /// Builds the wire bytes of a TXT question for `qname`, as would arrive
+    /// in a real report query.
+    fn question_message(qname: &str) -> Vec<u8> {
+        let name = Name::<Vec<u8>>::from_str(qname).unwrap();
+        let mut builder = MessageBuilder::new_vec().question();
+        builder.push((&name, Rtype::TXT, Class::IN)).unwrap();
+        builder.finish()
+    }
+
+    fn parse(service: &AgentService, qname: &str) -> Result<(u16, u16, String), QnameError> {
+        let octets = question_message(qname);
+        let message = Message::from_octets(octets.as_slice()).unwrap();
+        let question = message.sole_question().unwrap();
+        service
+            .parse_qname(question.qname())
+            .map(|(qtype, err_code, name)| (qtype, err_code, name.to_string()))
+    }
+
+    #[test]
+    fn accepts_a_well_formed_report_query() {
+        let service =
+            AgentService::new(Vec::new()).with_agent_domains(vec!["agent.example.com".to_string()]);
+        let (qtype, err_code, name) =
+            parse(&service, "_er.1.www.example.com.3._er.agent.example.com").unwrap();
+        assert_eq!(qtype, 1);
+        assert_eq!(err_code, 3);
+        assert_eq!(name, "www.example.com");
+    }
+
+    #[test]
+    fn rejects_too_few_labels_before_the_trailing_er_label() {
+        let service = AgentService::new(Vec::new());
+        let err = parse(&service, "_er.1._er.agent.example.com").unwrap_err();
+        assert!(matches!(err, QnameError::Malformed(_)));
+    }
+
+    #[test]
+    fn rejects_a_non_numeric_qtype_label() {
+        let service = AgentService::new(Vec::new());
+        let err = parse(
+            &service,
+            "_er.notanumber.www.example.com.3._er.agent.example.com",
+        )
+        .unwrap_err();
+        assert!(matches!(err, QnameError::Malformed(_)));
+    }
+
+    #[test]
+    fn rejects_a_query_for_an_unconfigured_agent_domain() {
+        let service =
+            AgentService::new(Vec::new()).with_agent_domains(vec!["agent.example.com".to_string()]);
+        let err = parse(&service, "_er.1.www.example.com.3._er.other.example.com").unwrap_err();
+        assert!(matches!(err, QnameError::WrongAgentDomain(_)));
+    }
+
+    /// Regression test for treating `agent_domains` as raw strings: a
+    /// trailing dot or differing capitalization in config must not cause a
+    /// legitimate report query to be REFUSED.
+    #[test]
+    fn matches_agent_domain_regardless_of_trailing_dot_or_case() {
+        let service = AgentService::new(Vec::new())
+            .with_agent_domains(vec!["Agent.Example.com.".to_string()]);
+        let result = parse(&service, "_er.1.www.example.com.3._er.agent.example.com");
+        assert!(result.is_ok(), "expected a match, got {result:?}");
+    }
+
+    #[test]
+    fn empty_agent_domains_accepts_any_domain() {
+        let service = AgentService::new(Vec::new());
+        let result = parse(&service, "_er.1.www.example.com.3._er.anything.example.org");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn with_agent_domains_skips_entries_that_fail_to_parse_as_names() {
+        let service = AgentService::new(Vec::new()).with_agent_domains(vec![
+            "not a valid domain..".to_string(),
+            "good.example.com".to_string(),
+        ]);
+        assert_eq!(service.agent_domains.len(), 1);
+    }
+}
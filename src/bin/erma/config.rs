@@ -0,0 +1,172 @@
+//! TOML configuration file support.
+//!
+//! `erma` can be configured entirely from the command line, but a config
+//! file (`--config`) lets a single instance declare the agent domain(s) it
+//! is authoritative for, listen on several sockets across transports, and
+//! set up its report sinks, without a wall of CLI flags. CLI flags are
+//! merged on top of the file: anything given on the command line augments
+//! or overrides the matching file setting, except listen sockets: when the
+//! file declares any, they are used as they are and `--addr`/`--port`/
+//! `--tls-port`/`--quic-port` are ignored.
+
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+//----------- ConfigError -------------------------------------------------------
+
+/// An error encountered while loading a config file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Io(std::io::Error),
+
+    /// The config file was not valid TOML, or didn't match the expected
+    /// schema.
+    Parse(toml::de::Error),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "I/O error reading config file: {err}"),
+            ConfigError::Parse(err) => write!(f, "invalid config file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+//----------- Transport ---------------------------------------------------------
+
+/// The wire transport a [`ListenSocket`] accepts connections over.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    Udp,
+    Tcp,
+    Tls,
+    Quic,
+}
+
+//----------- ListenSocket -------------------------------------------------------
+
+/// A single listen socket: an address, a port and the transport to accept
+/// on it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListenSocket {
+    pub address: IpAddr,
+    pub port: u16,
+    pub transport: Transport,
+}
+
+//----------- ReportConfig -------------------------------------------------------
+
+/// Report-sink settings, mirroring the `--no-stdout-reports` /
+/// `--report-*` CLI flags.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReportConfig {
+    /// Whether to log reports to stdout. Defaults to `true`.
+    #[serde(default = "ReportConfig::default_stdout")]
+    pub stdout: bool,
+
+    /// Append reports as JSON-lines records to this file, if set.
+    pub json_file: Option<PathBuf>,
+
+    /// Whether to forward reports to the local syslog daemon.
+    #[serde(default)]
+    pub syslog: bool,
+
+    /// POST reports as JSON to this HTTP endpoint, if set.
+    pub http_endpoint: Option<String>,
+}
+
+impl ReportConfig {
+    fn default_stdout() -> bool {
+        true
+    }
+}
+
+impl Default for ReportConfig {
+    /// Hand-written rather than derived, so that an absent `[report]`
+    /// table (or no config file at all) defaults `stdout` to `true` just
+    /// like a present-but-empty `[report]` table does via `#[serde(default
+    /// = "ReportConfig::default_stdout")]`. A derived `Default` would give
+    /// `stdout: false`, silently disabling the one sink that's supposed to
+    /// stay on by default.
+    fn default() -> Self {
+        Self {
+            stdout: Self::default_stdout(),
+            json_file: None,
+            syslog: false,
+            http_endpoint: None,
+        }
+    }
+}
+
+//----------- Config -------------------------------------------------------------
+
+/// The top-level `erma` config file schema.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Config {
+    /// The agent domain(s) this instance is authoritative for.
+    ///
+    /// A report query is only accepted if, after consuming its `_er`
+    /// label, the remaining labels exactly match one of these.
+    ///
+    /// DNSSEC signing (`key_file`) and zone-serving (`zonefile`) only
+    /// support a single apex per instance, and always use the first entry
+    /// here; additional domains still get report-QNAME validation, but no
+    /// DNSKEY or zone answers.
+    #[serde(default)]
+    pub agent_domains: Vec<String>,
+
+    /// The sockets to listen on.
+    #[serde(default)]
+    pub listen: Vec<ListenSocket>,
+
+    /// Report-sink settings.
+    #[serde(default)]
+    pub report: ReportConfig,
+
+    /// Path to a zone signing key file, as `--key-file`.
+    pub key_file: Option<PathBuf>,
+
+    /// Path to a PEM certificate chain, as `--tls-cert`.
+    pub tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM private key matching `tls_cert`, as `--tls-key`.
+    pub tls_key: Option<PathBuf>,
+
+    /// Path to a zonefile for the agent domain, as `--zonefile`.
+    ///
+    /// When set, this instance serves the agent domain as a complete
+    /// authoritative zone instead of only answering `_er` report queries.
+    pub zonefile: Option<PathBuf>,
+
+    /// How long minted RRSIGs stay valid for, in seconds, as
+    /// `--signature-validity-secs`.
+    pub signature_validity_secs: Option<u32>,
+}
+
+impl Config {
+    /// Loads and parses a config file.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
@@ -0,0 +1,145 @@
+//! A DNS-over-QUIC (RFC 9250) listener.
+//!
+//! Each query/response exchange happens on its own bidirectional QUIC
+//! stream, length-prefixed exactly like DNS-over-TCP. [`QuicListener`]
+//! accepts connections and, for each one, hands every bidirectional stream
+//! opened on it to [`StreamServer`](domain::net::server::stream::StreamServer)
+//! as if it were its own short-lived TCP connection, so the existing
+//! middleware chain and [`AgentService`](crate::agent::AgentService) handle
+//! it identically to the other transports.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use domain::net::server::listener::AsyncAccept;
+
+/// ALPN protocol id for DNS-over-QUIC, per RFC 9250 section 7.1.
+pub const DOQ_ALPN: &[u8] = b"doq";
+
+//----------- QuicBiStream -----------------------------------------------------
+
+/// A QUIC bidirectional stream, wrapped to look like a duplex byte stream
+/// (as [`StreamServer`](domain::net::server::stream::StreamServer) expects
+/// of a TCP connection).
+pub struct QuicBiStream {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl QuicBiStream {
+    fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+        Self { send, recv }
+    }
+}
+
+impl AsyncRead for QuicBiStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicBiStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(cx)
+    }
+}
+
+//----------- QuicListener -----------------------------------------------------
+
+/// An [`AsyncAccept`] implementation over a quinn QUIC endpoint, yielding
+/// one [`QuicBiStream`] per bidirectional stream opened by any client
+/// connection.
+pub struct QuicListener {
+    streams: Mutex<mpsc::Receiver<io::Result<(QuicBiStream, SocketAddr)>>>,
+}
+
+impl QuicListener {
+    /// Binds a QUIC endpoint at `addr` and starts accepting connections and
+    /// their bidirectional streams in the background.
+    pub fn bind(addr: SocketAddr, server_config: quinn::ServerConfig) -> io::Result<Self> {
+        let endpoint = quinn::Endpoint::server(server_config, addr)?;
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(Self::accept_connections(endpoint, tx));
+        Ok(Self {
+            streams: Mutex::new(rx),
+        })
+    }
+
+    async fn accept_connections(
+        endpoint: quinn::Endpoint,
+        tx: mpsc::Sender<io::Result<(QuicBiStream, SocketAddr)>>,
+    ) {
+        while let Some(connecting) = endpoint.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                match connecting.await {
+                    Ok(conn) => Self::accept_streams(conn, tx).await,
+                    Err(err) => warn!("QUIC handshake failed: {err}"),
+                }
+            });
+        }
+    }
+
+    async fn accept_streams(
+        conn: quinn::Connection,
+        tx: mpsc::Sender<io::Result<(QuicBiStream, SocketAddr)>>,
+    ) {
+        let addr = conn.remote_address();
+        loop {
+            match conn.accept_bi().await {
+                Ok((send, recv)) => {
+                    let stream = QuicBiStream::new(send, recv);
+                    if tx.send(Ok((stream, addr))).await.is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    warn!("QUIC connection from {addr} closed: {err}");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncAccept for QuicListener {
+    type Addr = SocketAddr;
+    type Stream = QuicBiStream;
+    type Future = std::future::Ready<io::Result<(Self::Stream, Self::Addr)>>;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<Self::Future>> {
+        let mut rx = self.streams.lock().unwrap();
+        match rx.poll_recv(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(item.map(std::future::ready)),
+            Poll::Ready(None) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "QUIC endpoint closed",
+            ))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
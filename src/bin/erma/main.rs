@@ -1,8 +1,16 @@
 mod agent;
+mod config;
+mod doq;
+mod signing;
+mod sink;
+mod tls;
+mod zone;
 
 use core::future::pending;
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use clap::Parser;
@@ -10,10 +18,11 @@ use rand::RngCore;
 use tokio::net::{TcpListener, UdpSocket};
 
 use agent::AgentService;
+use config::{Config, ListenSocket, Transport};
 use daemonbase::error::ExitError;
 use daemonbase::logging::{self, Logger};
 use daemonbase::process::{self, Process};
-use domain::base::RelativeName;
+use domain::base::Name;
 use domain::net::server::buf::VecBufSource;
 use domain::net::server::dgram::{self, DgramServer};
 use domain::net::server::middleware::builder::MiddlewareBuilder;
@@ -21,7 +30,12 @@ use domain::net::server::middleware::chain::MiddlewareChain;
 use domain::net::server::middleware::processors::cookies::CookiesMiddlewareProcessor;
 use domain::net::server::stream::StreamServer;
 use domain::net::server::{stream, ConnectionConfig};
-use tracing::{error, info};
+use doq::QuicListener;
+use signing::ZoneSigningKey;
+use sink::{HttpSink, JsonLinesSink, ReportSink, StdoutSink, SyslogSink};
+use tls::TlsListener;
+use tracing::{error, info, warn};
+use zone::Zone;
 
 //----------- Args -----------------------------------------------------------
 
@@ -39,6 +53,13 @@ pub struct Args {
     #[command(flatten)]
     process: process::Args,
 
+    /// Path to a TOML config file declaring the agent domain(s), listen
+    /// sockets and report-sink settings for this instance.
+    ///
+    /// Settings given on the command line are merged on top of the file.
+    #[arg(long = "config", value_name = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+
     /// The IP address to listen on
     #[arg(long = "addr", value_name = "LISTEN_ADDRESS", default_value = "[::]")]
     listen_address: String,
@@ -46,6 +67,207 @@ pub struct Args {
     /// The port to listen on
     #[arg(long = "port", value_name = "LISTEN_PORT", default_value = "53")]
     listen_port: u16,
+
+    /// The agent domain this instance serves reports for, e.g.
+    /// `agent.example.com`.
+    ///
+    /// Required to answer DNSKEY queries when `--key-file` is set.
+    #[arg(long = "agent-domain", value_name = "AGENT_DOMAIN")]
+    agent_domain: Option<String>,
+
+    /// Path to a zone signing key file, enabling DNSSEC signing of
+    /// responses for resolvers that set the EDNS DO bit.
+    ///
+    /// See [`signing::ZoneSigningKey::load`] for the expected file format.
+    #[arg(long = "key-file", value_name = "KEY_FILE")]
+    key_file: Option<PathBuf>,
+
+    /// Path to a PEM certificate chain for the DNS-over-TLS (RFC 7858)
+    /// listener.
+    ///
+    /// DoT is only started when both `--tls-cert` and `--tls-key` are set.
+    #[arg(long = "tls-cert", value_name = "TLS_CERT_FILE")]
+    tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM private key matching `--tls-cert`.
+    #[arg(long = "tls-key", value_name = "TLS_KEY_FILE")]
+    tls_key: Option<PathBuf>,
+
+    /// The port the DNS-over-TLS listener binds to.
+    #[arg(long = "tls-port", value_name = "TLS_PORT", default_value = "853")]
+    tls_port: u16,
+
+    /// The port the DNS-over-QUIC (RFC 9250) listener binds to.
+    ///
+    /// DoQ is only started when `--tls-cert` and `--tls-key` are set, since
+    /// QUIC requires the same certificate material as DoT.
+    #[arg(long = "quic-port", value_name = "QUIC_PORT", default_value = "853")]
+    quic_port: u16,
+
+    /// Disable the default stdout report sink.
+    ///
+    /// Useful when only the file/syslog/HTTP sinks below are wanted.
+    #[arg(long = "no-stdout-reports")]
+    no_stdout_reports: bool,
+
+    /// Append each report as a JSON-lines record to this file.
+    #[arg(long = "report-json-file", value_name = "PATH")]
+    report_json_file: Option<PathBuf>,
+
+    /// Forward each report to the local syslog daemon.
+    #[arg(long = "report-syslog")]
+    report_syslog: bool,
+
+    /// POST each report as JSON to this HTTP endpoint.
+    #[arg(long = "report-http-endpoint", value_name = "URL")]
+    report_http_endpoint: Option<String>,
+
+    /// Path to a zonefile for the agent domain.
+    ///
+    /// When set, this instance serves the agent domain as a complete
+    /// authoritative zone (SOA, NS, DNSKEY, etc.) alongside its `_er`
+    /// report-handling. Requires `--agent-domain` (or a config file's
+    /// `agent_domains`) to know the zone's apex.
+    #[arg(long = "zonefile", value_name = "ZONEFILE")]
+    zonefile: Option<PathBuf>,
+
+    /// How long minted RRSIGs stay valid for, in seconds.
+    ///
+    /// Only meaningful when `--key-file` (or a config file's `key_file`) is
+    /// set. Defaults to [`agent::DEFAULT_SIGNATURE_VALIDITY_SECS`].
+    #[arg(long = "signature-validity-secs", value_name = "SECONDS")]
+    signature_validity_secs: Option<u32>,
+}
+
+//----------- EffectiveConfig --------------------------------------------------
+
+/// The settings that actually govern this run, after merging an optional
+/// `--config` file with CLI overrides (CLI wins wherever both set a scalar
+/// value; `--agent-domain` is added to the file's `agent_domains`; the
+/// file's `listen` sockets, when present, are used in place of the ones
+/// built from CLI flags).
+struct EffectiveConfig {
+    agent_domains: Vec<String>,
+    key_file: Option<PathBuf>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    listen: Vec<ListenSocket>,
+    report_stdout: bool,
+    report_json_file: Option<PathBuf>,
+    report_syslog: bool,
+    report_http_endpoint: Option<String>,
+    zonefile: Option<PathBuf>,
+    signature_validity_secs: u32,
+}
+
+impl EffectiveConfig {
+    fn merge(args: &Args) -> Self {
+        let config = match &args.config {
+            Some(path) => Config::load(path).unwrap_or_else(|err| {
+                error!("Unable to load config file {path:?}: {err}");
+                std::process::exit(1);
+            }),
+            None => Config::default(),
+        };
+
+        let mut agent_domains = config.agent_domains;
+        if let Some(agent_domain) = &args.agent_domain {
+            if !agent_domains
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(agent_domain))
+            {
+                agent_domains.push(agent_domain.clone());
+            }
+        }
+
+        let listen = if !config.listen.is_empty() {
+            config.listen
+        } else {
+            default_listen_sockets(args)
+        };
+
+        Self {
+            agent_domains,
+            key_file: args.key_file.clone().or(config.key_file),
+            tls_cert: args.tls_cert.clone().or(config.tls_cert),
+            tls_key: args.tls_key.clone().or(config.tls_key),
+            listen,
+            report_stdout: !args.no_stdout_reports && config.report.stdout,
+            report_json_file: args.report_json_file.clone().or(config.report.json_file),
+            report_syslog: args.report_syslog || config.report.syslog,
+            report_http_endpoint: args
+                .report_http_endpoint
+                .clone()
+                .or(config.report.http_endpoint),
+            zonefile: args.zonefile.clone().or(config.zonefile),
+            signature_validity_secs: clamp_signature_validity_secs(
+                args.signature_validity_secs
+                    .or(config.signature_validity_secs)
+                    .unwrap_or(agent::DEFAULT_SIGNATURE_VALIDITY_SECS),
+            ),
+        }
+    }
+}
+
+/// Clamps an operator-supplied signature validity to
+/// [`agent::MAX_SIGNATURE_VALIDITY_SECS`], warning if it had to.
+///
+/// `--signature-validity-secs` is otherwise unvalidated, and a large enough
+/// value would, combined with `saturating_add`, silently clamp every
+/// RRSIG's expiration to `u32::MAX` instead of the admin's intended
+/// duration; warning here surfaces the misconfiguration instead.
+fn clamp_signature_validity_secs(secs: u32) -> u32 {
+    if secs > agent::MAX_SIGNATURE_VALIDITY_SECS {
+        warn!(
+            "--signature-validity-secs {secs} is implausibly large; clamping to {}",
+            agent::MAX_SIGNATURE_VALIDITY_SECS
+        );
+        agent::MAX_SIGNATURE_VALIDITY_SECS
+    } else {
+        secs
+    }
+}
+
+/// The listen sockets to use when the config file doesn't declare any:
+/// UDP+TCP on `--addr`/`--port`, plus TLS/QUIC on `--tls-port`/
+/// `--quic-port` if `--tls-cert`/`--tls-key` are set.
+fn default_listen_sockets(args: &Args) -> Vec<ListenSocket> {
+    let address: IpAddr = args
+        .listen_address
+        .trim_matches(['[', ']'])
+        .parse()
+        .unwrap_or_else(|err| {
+            error!("Invalid --addr {}: {err}", args.listen_address);
+            std::process::exit(1);
+        });
+
+    let mut listen = vec![
+        ListenSocket {
+            address,
+            port: args.listen_port,
+            transport: Transport::Udp,
+        },
+        ListenSocket {
+            address,
+            port: args.listen_port,
+            transport: Transport::Tcp,
+        },
+    ];
+
+    if args.tls_cert.is_some() && args.tls_key.is_some() {
+        listen.push(ListenSocket {
+            address,
+            port: args.tls_port,
+            transport: Transport::Tls,
+        });
+        listen.push(ListenSocket {
+            address,
+            port: args.quic_port,
+            transport: Transport::Quic,
+        });
+    }
+
+    listen
 }
 
 //----------- init_middleware() ----------------------------------------------
@@ -59,12 +281,99 @@ fn init_middleware() -> MiddlewareChain<Vec<u8>, Vec<u8>> {
     middleware.build()
 }
 
+//----------- init_sinks() -----------------------------------------------------
+
+fn init_sinks(config: &EffectiveConfig) -> Vec<Box<dyn ReportSink>> {
+    let mut sinks: Vec<Box<dyn ReportSink>> = Vec::new();
+
+    if config.report_stdout {
+        sinks.push(Box::new(StdoutSink));
+    }
+
+    if let Some(path) = &config.report_json_file {
+        match JsonLinesSink::open(path) {
+            Ok(sink) => sinks.push(Box::new(sink)),
+            Err(err) => {
+                error!("Unable to open report JSON-lines file {path:?}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if config.report_syslog {
+        match SyslogSink::connect() {
+            Ok(sink) => sinks.push(Box::new(sink)),
+            Err(err) => {
+                error!("Unable to connect to syslog: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(endpoint) = &config.report_http_endpoint {
+        sinks.push(Box::new(HttpSink::new(endpoint.clone())));
+    }
+
+    sinks
+}
+
 //----------- init_service() -------------------------------------------------
 
-fn init_service() -> Arc<AgentService<impl Fn(u16, u16, RelativeName<Vec<u8>>)>> {
-    let svc = AgentService::new(|qtype, edns_err_code, qname| {
-        println!("{qtype},{edns_err_code},{qname}")
-    });
+fn init_service(config: &EffectiveConfig) -> Arc<AgentService> {
+    let sinks = init_sinks(config);
+
+    if config.agent_domains.len() > 1 && (config.key_file.is_some() || config.zonefile.is_some()) {
+        warn!(
+            "Multiple agent domains are configured, but DNSSEC signing and zone-serving only \
+             support one apex per instance; {:?} will be used for the DNSKEY/zone apex, the \
+             rest only get report-QNAME validation",
+            config.agent_domains[0],
+        );
+    }
+
+    let mut svc = match &config.key_file {
+        Some(key_file) => {
+            let agent_domain = config.agent_domains.first().unwrap_or_else(|| {
+                error!("An agent domain (--agent-domain or config's agent_domains) is required when a key file is set");
+                std::process::exit(1);
+            });
+            let apex = Name::<Vec<u8>>::from_str(agent_domain).unwrap_or_else(|err| {
+                error!("Invalid agent domain {agent_domain}: {err}");
+                std::process::exit(1);
+            });
+            let signing_key = ZoneSigningKey::load(key_file).unwrap_or_else(|err| {
+                error!("Unable to load zone signing key from {key_file:?}: {err}");
+                std::process::exit(1);
+            });
+            info!(
+                algorithm = ?signing_key.algorithm(),
+                key_tag = signing_key.key_tag(),
+                "Loaded zone signing key"
+            );
+            AgentService::with_signing_key(sinks, signing_key, apex)
+        }
+        None => AgentService::new(sinks),
+    }
+    .with_agent_domains(config.agent_domains.clone())
+    .with_signature_validity_secs(config.signature_validity_secs);
+
+    if let Some(zonefile) = &config.zonefile {
+        let agent_domain = config.agent_domains.first().unwrap_or_else(|| {
+            error!("An agent domain (--agent-domain or config's agent_domains) is required when a zonefile is set");
+            std::process::exit(1);
+        });
+        let apex = Name::<Vec<u8>>::from_str(agent_domain).unwrap_or_else(|err| {
+            error!("Invalid agent domain {agent_domain}: {err}");
+            std::process::exit(1);
+        });
+        let zone = Zone::load(zonefile, apex).unwrap_or_else(|err| {
+            error!("Unable to load zonefile {zonefile:?}: {err}");
+            std::process::exit(1);
+        });
+        info!(zonefile = ?zonefile, "Loaded authoritative zone");
+        svc = svc.with_zone(zone);
+    }
+
     Arc::new(svc)
 }
 
@@ -81,50 +390,213 @@ async fn main() -> Result<(), ExitError> {
     let log = Logger::from_config(&args.log.to_config())?;
     log.switch_logging(args.detach)?;
 
-    let bind_address = format!("{}:{}", args.listen_address, args.listen_port);
-    let bind_address = bind_address.parse::<SocketAddr>().unwrap();
-
     let mut process = Process::from_config(args.process.into_config());
     process.setup_daemon(args.detach)?;
 
     process.drop_privileges()?;
 
     // -----------------------------------------------------------------------
-    // Create a service with accompanying middleware chain to answer incoming
+    // Merge the config file (if any) with CLI overrides, then create a
+    // service with accompanying middleware chain to answer incoming
     // requests.
     // https://www.rfc-editor.org/rfc/rfc9567#section-6.3-2 "The monitoring
     // agent SHOULD respond to queries received over UDP that have no DNS
     // Cookie set with a response that has the truncation bit (TC bit) set to
     // challenge the resolver to requery over TCP."
+    let effective_config = EffectiveConfig::merge(&args);
     let middleware = init_middleware();
-    let svc = init_service();
+    let svc = init_service(&effective_config);
 
-    // -----------------------------------------------------------------------
-    // Run a UDP DNS server.
-    let Ok(udpsocket) = UdpSocket::bind(bind_address).await else {
-        error!("Unable to bind to UDP address {bind_address}");
-        std::process::exit(1);
+    let tls_config = match (&effective_config.tls_cert, &effective_config.tls_key) {
+        (Some(tls_cert), Some(tls_key)) => Some(
+            tls::load_tls_config(tls_cert, tls_key).unwrap_or_else(|err| {
+                error!("Unable to load TLS certificate/key: {err}");
+                std::process::exit(1);
+            }),
+        ),
+        _ => None,
     };
 
-    let mut config = dgram::Config::default();
-    config.set_middleware_chain(middleware.clone());
-    let srv = DgramServer::with_config(udpsocket, VecBufSource, svc.clone(), config);
-    tokio::spawn(async move { srv.run().await });
-
     // -----------------------------------------------------------------------
-    // Run a TCP DNS server.
-    let Ok(listener) = TcpListener::bind(bind_address).await else {
-        error!("Unable to bind to UDP address {bind_address}");
-        std::process::exit(1);
-    };
-
+    // Start every configured listen socket, sharing the middleware chain
+    // and service across all of them.
     let mut conn_config = ConnectionConfig::default();
     conn_config.set_middleware_chain(middleware.clone());
-    let mut config = stream::Config::default();
-    config.set_connection_config(conn_config);
-    let srv = StreamServer::with_config(listener, VecBufSource, svc, config);
-    tokio::spawn(async move { srv.run().await });
+
+    for socket in &effective_config.listen {
+        let bind_address = SocketAddr::new(socket.address, socket.port);
+
+        match socket.transport {
+            Transport::Udp => {
+                let Ok(udpsocket) = UdpSocket::bind(bind_address).await else {
+                    error!("Unable to bind to UDP address {bind_address}");
+                    std::process::exit(1);
+                };
+                let mut config = dgram::Config::default();
+                config.set_middleware_chain(middleware.clone());
+                let srv = DgramServer::with_config(udpsocket, VecBufSource, svc.clone(), config);
+                tokio::spawn(async move { srv.run().await });
+            }
+
+            Transport::Tcp => {
+                let Ok(listener) = TcpListener::bind(bind_address).await else {
+                    error!("Unable to bind to TCP address {bind_address}");
+                    std::process::exit(1);
+                };
+                let mut config = stream::Config::default();
+                config.set_connection_config(conn_config.clone());
+                let srv = StreamServer::with_config(listener, VecBufSource, svc.clone(), config);
+                tokio::spawn(async move { srv.run().await });
+            }
+
+            Transport::Tls => {
+                let Some(tls_config) = &tls_config else {
+                    error!("Cannot listen for DoT on {bind_address}: no --tls-cert/--tls-key configured");
+                    std::process::exit(1);
+                };
+                let Ok(tls_tcp_listener) = TcpListener::bind(bind_address).await else {
+                    error!("Unable to bind to DoT address {bind_address}");
+                    std::process::exit(1);
+                };
+                let tls_listener = TlsListener::new(tls_tcp_listener, tls_config.clone());
+                let mut config = stream::Config::default();
+                config.set_connection_config(conn_config.clone());
+                let srv =
+                    StreamServer::with_config(tls_listener, VecBufSource, svc.clone(), config);
+                tokio::spawn(async move { srv.run().await });
+            }
+
+            Transport::Quic => {
+                let Some(tls_config) = &tls_config else {
+                    error!("Cannot listen for DoQ on {bind_address}: no --tls-cert/--tls-key configured");
+                    std::process::exit(1);
+                };
+                let mut quic_server_crypto = (**tls_config).clone();
+                quic_server_crypto.alpn_protocols = vec![doq::DOQ_ALPN.to_vec()];
+                let quic_crypto =
+                    quinn::crypto::rustls::QuicServerConfig::try_from(quic_server_crypto)
+                        .unwrap_or_else(|err| {
+                            error!("Invalid QUIC TLS configuration: {err}");
+                            std::process::exit(1);
+                        });
+                let quic_server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_crypto));
+                let quic_listener = QuicListener::bind(bind_address, quic_server_config)
+                    .unwrap_or_else(|err| {
+                        error!("Unable to bind to DoQ address {bind_address}: {err}");
+                        std::process::exit(1);
+                    });
+                let mut config = stream::Config::default();
+                config.set_connection_config(conn_config.clone());
+                let srv =
+                    StreamServer::with_config(quic_listener, VecBufSource, svc.clone(), config);
+                tokio::spawn(async move { srv.run().await });
+            }
+        }
+    }
 
     // Run until stopped.
     pending().await
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Writes `contents` to a uniquely-named temp TOML file and returns its
+    /// path.
+    fn write_temp_config(contents: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "erma-test-config-{}-{unique}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn merge_uses_built_in_defaults_with_no_config_or_flags() {
+        let args = Args::parse_from(["erma"]);
+        let effective = EffectiveConfig::merge(&args);
+
+        assert!(effective.agent_domains.is_empty());
+        assert_eq!(
+            effective.signature_validity_secs,
+            agent::DEFAULT_SIGNATURE_VALIDITY_SECS
+        );
+        assert!(effective.key_file.is_none());
+        assert!(
+            !effective.listen.is_empty(),
+            "falls back to default_listen_sockets"
+        );
+        assert!(
+            effective.report_stdout,
+            "stdout reporting must default to on"
+        );
+    }
+
+    #[test]
+    fn merge_takes_file_only_settings_when_cli_omits_them() {
+        let path = write_temp_config(
+            r#"
+            agent_domains = ["file.example.com"]
+            signature_validity_secs = 1234
+            "#,
+        );
+        let args = Args::parse_from(["erma", "--config", path.to_str().unwrap()]);
+        let effective = EffectiveConfig::merge(&args);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            effective.agent_domains,
+            vec!["file.example.com".to_string()]
+        );
+        assert_eq!(effective.signature_validity_secs, 1234);
+    }
+
+    #[test]
+    fn merge_prefers_cli_overrides_over_the_config_file() {
+        let path = write_temp_config(
+            r#"
+            agent_domains = ["file.example.com"]
+            signature_validity_secs = 1234
+            "#,
+        );
+        let args = Args::parse_from([
+            "erma",
+            "--config",
+            path.to_str().unwrap(),
+            "--agent-domain",
+            "cli.example.com",
+            "--signature-validity-secs",
+            "42",
+        ]);
+        let effective = EffectiveConfig::merge(&args);
+        std::fs::remove_file(&path).ok();
+
+        // CLI appends rather than replaces the domain list (see `merge`'s
+        // dedup-by-case-insensitive-match logic)...
+        assert!(effective
+            .agent_domains
+            .contains(&"file.example.com".to_string()));
+        assert!(effective
+            .agent_domains
+            .contains(&"cli.example.com".to_string()));
+        // ...but a scalar setting like signature validity is CLI-wins.
+        assert_eq!(effective.signature_validity_secs, 42);
+    }
+
+    #[test]
+    fn merge_clamps_an_implausibly_large_signature_validity() {
+        let args = Args::parse_from(["erma", "--signature-validity-secs", &u32::MAX.to_string()]);
+        let effective = EffectiveConfig::merge(&args);
+
+        assert_eq!(
+            effective.signature_validity_secs,
+            agent::MAX_SIGNATURE_VALIDITY_SECS
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use std::fs;
+use std::path::Path;
+
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING,
+    ECDSA_P384_SHA384_FIXED_SIGNING,
+};
+
+use domain::base::iana::{Class, SecAlg};
+use domain::base::{Name, Rtype, ToLabelIter, Ttl};
+use domain::rdata::rfc4034::{Dnskey, Rrsig};
+
+//----------- SignError -------------------------------------------------------
+
+/// An error encountered while loading or using a zone signing key.
+#[derive(Debug)]
+pub enum SignError {
+    /// The key file could not be read.
+    Io(std::io::Error),
+
+    /// The key file was malformed or named an unsupported algorithm.
+    Format(String),
+
+    /// The cryptographic backend rejected the key material or signing
+    /// operation.
+    Crypto(String),
+}
+
+impl std::fmt::Display for SignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignError::Io(err) => write!(f, "I/O error reading key file: {err}"),
+            SignError::Format(msg) => write!(f, "malformed key file: {msg}"),
+            SignError::Crypto(msg) => write!(f, "signing error: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
+
+//----------- KeyPairImpl -------------------------------------------------------
+
+/// The algorithm-specific key material backing a [`ZoneSigningKey`].
+enum KeyPairImpl {
+    EcdsaP256(EcdsaKeyPair),
+    EcdsaP384(EcdsaKeyPair),
+    Ed25519(Ed25519KeyPair),
+}
+
+//----------- ZoneSigningKey ---------------------------------------------------
+
+/// A zone signing key used to produce RRSIG records for the agent domain.
+///
+/// Supports the algorithm set modern DNS stacks ship: ECDSAP256SHA256,
+/// ECDSAP384SHA384 and ED25519.
+pub struct ZoneSigningKey {
+    algorithm: SecAlg,
+    key_pair: KeyPairImpl,
+    key_tag: u16,
+    dnskey: Dnskey<Vec<u8>>,
+}
+
+impl ZoneSigningKey {
+    /// Loads a zone signing key from the simple key file format used by
+    /// `erma`: a first line naming the algorithm (`ECDSAP256SHA256`,
+    /// `ECDSAP384SHA384` or `ED25519`) followed by a second line containing
+    /// the base64-encoded PKCS#8 private key.
+    pub fn load(path: &Path) -> Result<Self, SignError> {
+        let contents = fs::read_to_string(path).map_err(SignError::Io)?;
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+
+        let algorithm = lines
+            .next()
+            .ok_or_else(|| SignError::Format("missing algorithm line".to_string()))?
+            .trim();
+
+        let pkcs8_b64 = lines
+            .next()
+            .ok_or_else(|| SignError::Format("missing private key line".to_string()))?
+            .trim();
+
+        let pkcs8 = base64::decode(pkcs8_b64)
+            .map_err(|err| SignError::Format(format!("invalid base64 private key: {err}")))?;
+
+        let rng = SystemRandom::new();
+
+        let (algorithm, key_pair, flags) = match algorithm {
+            "ECDSAP256SHA256" => {
+                let kp = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+                    .map_err(|err| SignError::Crypto(err.to_string()))?;
+                (SecAlg::ECDSAP256SHA256, KeyPairImpl::EcdsaP256(kp), 256)
+            }
+            "ECDSAP384SHA384" => {
+                let kp = EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, &pkcs8, &rng)
+                    .map_err(|err| SignError::Crypto(err.to_string()))?;
+                (SecAlg::ECDSAP384SHA384, KeyPairImpl::EcdsaP384(kp), 256)
+            }
+            "ED25519" => {
+                let kp = Ed25519KeyPair::from_pkcs8(&pkcs8)
+                    .map_err(|err| SignError::Crypto(err.to_string()))?;
+                (SecAlg::ED25519, KeyPairImpl::Ed25519(kp), 256)
+            }
+            other => {
+                return Err(SignError::Format(format!(
+                    "unsupported algorithm {other}, expected one of ECDSAP256SHA256, \
+                     ECDSAP384SHA384, ED25519"
+                )))
+            }
+        };
+
+        let public_key = match &key_pair {
+            // ring yields the uncompressed point (a 0x04 octet, then X and
+            // Y); an ECDSA DNSKEY (RFC 6605) carries only X and Y.
+            KeyPairImpl::EcdsaP256(kp) => kp.public_key().as_ref()[1..].to_vec(),
+            KeyPairImpl::EcdsaP384(kp) => kp.public_key().as_ref()[1..].to_vec(),
+            KeyPairImpl::Ed25519(kp) => kp.public_key().as_ref().to_vec(),
+        };
+
+        let dnskey = Dnskey::new(flags, 3, algorithm, public_key)
+            .map_err(|err| SignError::Format(format!("invalid DNSKEY rdata: {err}")))?;
+        let key_tag = dnskey.key_tag();
+
+        Ok(Self {
+            algorithm,
+            key_pair,
+            key_tag,
+            dnskey,
+        })
+    }
+
+    /// The DNSKEY RRSET rdata for this key, served at the zone apex.
+    pub fn dnskey(&self) -> &Dnskey<Vec<u8>> {
+        &self.dnskey
+    }
+
+    /// The key tag as computed from the DNSKEY rdata, used in RRSIG rdata.
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// The DNSSEC algorithm this key signs with.
+    pub fn algorithm(&self) -> SecAlg {
+        self.algorithm
+    }
+
+    /// Signs `signed_data` (the RRSIG RDATA without the signature field,
+    /// followed by each covered RR in canonical wire form) and returns the
+    /// raw signature bytes.
+    fn sign(&self, signed_data: &[u8]) -> Result<Vec<u8>, SignError> {
+        let rng = SystemRandom::new();
+        match &self.key_pair {
+            KeyPairImpl::EcdsaP256(kp) => kp
+                .sign(&rng, signed_data)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|err| SignError::Crypto(err.to_string())),
+            KeyPairImpl::EcdsaP384(kp) => kp
+                .sign(&rng, signed_data)
+                .map(|sig| sig.as_ref().to_vec())
+                .map_err(|err| SignError::Crypto(err.to_string())),
+            KeyPairImpl::Ed25519(kp) => Ok(kp.sign(signed_data).as_ref().to_vec()),
+        }
+    }
+
+    /// Builds an RRSIG record covering a single RRset, per RFC 4034 section
+    /// 3.1.
+    ///
+    /// `rrset` holds the wire-form RDATA of each RR in the set; `owner`,
+    /// `class`, `rtype` and `original_ttl` are shared by every RR in the
+    /// set, as required for a valid RRSIG.
+    pub fn sign_rrset(
+        &self,
+        owner: &Name<Vec<u8>>,
+        class: Class,
+        rtype: Rtype,
+        original_ttl: Ttl,
+        inception: u32,
+        expiration: u32,
+        rrset: &[Vec<u8>],
+    ) -> Result<Rrsig<Vec<u8>, Name<Vec<u8>>>, SignError> {
+        let labels = owner.iter_labels().filter(|label| !label.is_root()).count() as u8;
+
+        let mut signed_data = Vec::new();
+        append_rrsig_rdata_without_signature(
+            &mut signed_data,
+            rtype,
+            self.algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            self.key_tag,
+            owner,
+        );
+        // RFC 4034 section 6.3 requires RRs within an RRset to be put in
+        // canonical order (by unsigned-octet comparison of their RDATA wire
+        // form) before hashing; `Vec<u8>`'s `Ord` impl is already that
+        // byte-lexicographic comparison, so a plain sort suffices. This is a
+        // no-op for the single-record RRsets we mint ourselves (TXT ack,
+        // DNSKEY), but matters for zone-loaded RRsets, which may not already
+        // be in canonical order in the zonefile.
+        let mut canonical_rrset: Vec<&Vec<u8>> = rrset.iter().collect();
+        canonical_rrset.sort();
+
+        for rdata in canonical_rrset {
+            append_canonical_name(&mut signed_data, owner);
+            signed_data.extend_from_slice(&u16::from(class).to_be_bytes());
+            signed_data.extend_from_slice(&u16::from(rtype).to_be_bytes());
+            signed_data.extend_from_slice(&original_ttl.as_secs().to_be_bytes());
+            signed_data.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            signed_data.extend_from_slice(rdata);
+        }
+
+        let signature = self.sign(&signed_data)?;
+
+        Rrsig::new(
+            rtype,
+            self.algorithm,
+            labels,
+            original_ttl,
+            expiration,
+            inception,
+            self.key_tag,
+            owner.clone(),
+            signature,
+        )
+        .map_err(|err| SignError::Format(format!("invalid RRSIG rdata: {err}")))
+    }
+}
+
+/// Appends the canonical wire form of `name` (lowercased labels, no
+/// compression) to `buf`.
+fn append_canonical_name(buf: &mut Vec<u8>, name: &Name<Vec<u8>>) {
+    for label in name.iter_labels() {
+        let slice = label.as_slice();
+        buf.push(slice.len() as u8);
+        buf.extend(slice.iter().map(u8::to_ascii_lowercase));
+    }
+}
+
+/// Appends the RRSIG RDATA fields preceding the signature, per RFC 4034
+/// section 3.1.
+#[allow(clippy::too_many_arguments)]
+fn append_rrsig_rdata_without_signature(
+    buf: &mut Vec<u8>,
+    type_covered: Rtype,
+    algorithm: SecAlg,
+    labels: u8,
+    original_ttl: Ttl,
+    expiration: u32,
+    inception: u32,
+    key_tag: u16,
+    signer_name: &Name<Vec<u8>>,
+) {
+    buf.extend_from_slice(&u16::from(type_covered).to_be_bytes());
+    buf.push(u8::from(algorithm));
+    buf.push(labels);
+    buf.extend_from_slice(&original_ttl.as_secs().to_be_bytes());
+    buf.extend_from_slice(&expiration.to_be_bytes());
+    buf.extend_from_slice(&inception.to_be_bytes());
+    buf.extend_from_slice(&key_tag.to_be_bytes());
+    append_canonical_name(buf, signer_name);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::str::FromStr;
+
+    use ring::rand::SystemRandom;
+    use ring::signature::{
+        EcdsaKeyPair, KeyPair, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED,
+        ECDSA_P256_SHA256_FIXED_SIGNING,
+    };
+
+    use domain::base::iana::Class;
+    use domain::base::Name;
+
+    use super::*;
+
+    /// Writes a freshly generated ECDSAP256SHA256 key to a temp file in
+    /// `ZoneSigningKey::load`'s expected format, returning the path and the
+    /// raw PKCS#8 bytes, so the test can independently re-derive the public
+    /// key instead of trusting `ZoneSigningKey`'s own accessor for it.
+    fn write_test_key() -> (std::path::PathBuf, Vec<u8>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .expect("key generation");
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("erma-test-key-{}-{unique}.txt", std::process::id()));
+        let mut file = std::fs::File::create(&path).expect("create temp key file");
+        writeln!(file, "ECDSAP256SHA256").unwrap();
+        writeln!(file, "{}", base64::encode(pkcs8.as_ref())).unwrap();
+        (path, pkcs8.as_ref().to_vec())
+    }
+
+    #[test]
+    fn sign_rrset_produces_a_signature_that_verifies() {
+        let (path, pkcs8) = write_test_key();
+        let signing_key = ZoneSigningKey::load(&path).expect("load key");
+        std::fs::remove_file(&path).ok();
+
+        let rng = SystemRandom::new();
+        let kp = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+            .expect("rederive keypair");
+        let public_key = kp.public_key().as_ref().to_vec();
+
+        let owner = Name::<Vec<u8>>::from_str("www.example.com.").unwrap();
+        let ttl = Ttl::from_secs(3600);
+        let rrset = vec![b"hello".to_vec()];
+        let inception = 1_700_000_000u32;
+        let expiration = inception + 86400;
+
+        let rrsig = signing_key
+            .sign_rrset(
+                &owner,
+                Class::IN,
+                Rtype::TXT,
+                ttl,
+                inception,
+                expiration,
+                &rrset,
+            )
+            .expect("signing should succeed");
+
+        // Reconstruct the signed-data input independently (not by calling
+        // back into `sign_rrset`) and verify the signature against it with
+        // `ring` directly, so this catches a mismatch between the RRSIG
+        // wire format we construct and what a validator would compute.
+        let labels = owner.iter_labels().filter(|label| !label.is_root()).count() as u8;
+        let mut signed_data = Vec::new();
+        append_rrsig_rdata_without_signature(
+            &mut signed_data,
+            Rtype::TXT,
+            signing_key.algorithm(),
+            labels,
+            ttl,
+            expiration,
+            inception,
+            signing_key.key_tag(),
+            &owner,
+        );
+        for rdata in &rrset {
+            append_canonical_name(&mut signed_data, &owner);
+            signed_data.extend_from_slice(&u16::from(Class::IN).to_be_bytes());
+            signed_data.extend_from_slice(&u16::from(Rtype::TXT).to_be_bytes());
+            signed_data.extend_from_slice(&ttl.as_secs().to_be_bytes());
+            signed_data.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            signed_data.extend_from_slice(rdata);
+        }
+
+        let verifier = UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &public_key);
+        verifier
+            .verify(&signed_data, rrsig.signature())
+            .expect("signature must verify against the independently-reconstructed signed data");
+    }
+
+    #[test]
+    fn sign_rrset_rrsig_fields_match_inputs() {
+        let (path, _pkcs8) = write_test_key();
+        let signing_key = ZoneSigningKey::load(&path).expect("load key");
+        std::fs::remove_file(&path).ok();
+
+        let owner = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let ttl = Ttl::from_secs(300);
+        let rrset = vec![b"rdata".to_vec()];
+
+        let rrsig = signing_key
+            .sign_rrset(&owner, Class::IN, Rtype::A, ttl, 1000, 2000, &rrset)
+            .expect("signing should succeed");
+
+        assert_eq!(rrsig.type_covered(), Rtype::A);
+        assert_eq!(rrsig.algorithm(), signing_key.algorithm());
+        assert_eq!(rrsig.original_ttl(), ttl);
+        assert_eq!(rrsig.inception().into_int(), 1000);
+        assert_eq!(rrsig.expiration().into_int(), 2000);
+        assert_eq!(rrsig.key_tag(), signing_key.key_tag());
+        assert_eq!(rrsig.signer_name(), &owner);
+    }
+
+    #[test]
+    fn sign_rrset_sorts_rdata_into_canonical_order_before_signing() {
+        let (path, pkcs8) = write_test_key();
+        let signing_key = ZoneSigningKey::load(&path).expect("load key");
+        std::fs::remove_file(&path).ok();
+
+        let rng = SystemRandom::new();
+        let kp = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+            .expect("rederive keypair");
+        let public_key = kp.public_key().as_ref().to_vec();
+
+        let owner = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let ttl = Ttl::from_secs(300);
+
+        // Two RRs given to `sign_rrset` in reverse canonical
+        // (byte-lexicographic) order.
+        let reversed = vec![b"bbbbb".to_vec(), b"aaaaa".to_vec()];
+        let inception = 1000;
+        let expiration = 2000;
+
+        let rrsig = signing_key
+            .sign_rrset(
+                &owner,
+                Class::IN,
+                Rtype::A,
+                ttl,
+                inception,
+                expiration,
+                &reversed,
+            )
+            .expect("signing should succeed");
+
+        // Independently reconstruct the signed-data input in canonical
+        // (sorted) order and check the signature verifies against it; if
+        // `sign_rrset` didn't sort its input, the signature would instead
+        // only verify against the caller's original, unsorted order.
+        let labels = owner.iter_labels().filter(|label| !label.is_root()).count() as u8;
+        let mut signed_data = Vec::new();
+        append_rrsig_rdata_without_signature(
+            &mut signed_data,
+            Rtype::A,
+            signing_key.algorithm(),
+            labels,
+            ttl,
+            expiration,
+            inception,
+            signing_key.key_tag(),
+            &owner,
+        );
+        for rdata in [b"aaaaa".to_vec(), b"bbbbb".to_vec()] {
+            append_canonical_name(&mut signed_data, &owner);
+            signed_data.extend_from_slice(&u16::from(Class::IN).to_be_bytes());
+            signed_data.extend_from_slice(&u16::from(Rtype::A).to_be_bytes());
+            signed_data.extend_from_slice(&ttl.as_secs().to_be_bytes());
+            signed_data.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            signed_data.extend_from_slice(&rdata);
+        }
+
+        let verifier = UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &public_key);
+        verifier
+            .verify(&signed_data, rrsig.signature())
+            .expect("signature must verify against the canonically-ordered signed data");
+    }
+
+    #[test]
+    fn load_is_deterministic_about_the_key_tag() {
+        let (path, _pkcs8) = write_test_key();
+        let a = ZoneSigningKey::load(&path).expect("load key");
+        let b = ZoneSigningKey::load(&path).expect("load key again");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(a.key_tag(), b.key_tag());
+        assert_eq!(a.dnskey().key_tag(), a.key_tag());
+    }
+
+    #[test]
+    fn load_rejects_unknown_algorithm() {
+        let path =
+            std::env::temp_dir().join(format!("erma-test-bad-key-{}.txt", std::process::id()));
+        std::fs::write(&path, "NOT-A-REAL-ALGORITHM\nAAAA\n").unwrap();
+        let result = ZoneSigningKey::load(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(result, Err(SignError::Format(_))));
+    }
+}
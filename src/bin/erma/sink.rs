@@ -0,0 +1,384 @@
+//! Report sinks: pluggable destinations for decoded RFC 9567 reports.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use domain::base::{RelativeName, Rtype};
+use serde::Serialize;
+use tracing::warn;
+
+//----------- ReportSink -------------------------------------------------------
+
+/// A destination that a decoded RFC 9567 error report can be routed to.
+///
+/// `AgentService` fans every report out to a `Vec<Box<dyn ReportSink>>`
+/// rather than a single callback, so operators can combine sinks (e.g. log
+/// to stdout for debugging while also forwarding to an incident API).
+pub trait ReportSink: Send + Sync {
+    /// Handles one decoded report.
+    ///
+    /// `qtype`/`err_code` are the raw values carried in the report QNAME;
+    /// `qname` is the original name the resolver was querying when it hit
+    /// the EDNS error; `client_addr` is the address of the resolver that
+    /// sent the report.
+    fn report(
+        &self,
+        qtype: u16,
+        err_code: u16,
+        qname: &RelativeName<Vec<u8>>,
+        client_addr: SocketAddr,
+    );
+}
+
+/// Returns the RFC 8914 Extended DNS Error name for `code`, or `"UNKNOWN"`
+/// if it isn't one of the currently assigned codes.
+fn ede_code_name(code: u16) -> &'static str {
+    match code {
+        0 => "Other",
+        1 => "Unsupported DNSKEY Algorithm",
+        2 => "Unsupported DS Digest Type",
+        3 => "Stale Answer",
+        4 => "Forged Answer",
+        5 => "DNSSEC Indeterminate",
+        6 => "DNSSEC Bogus",
+        7 => "Signature Expired",
+        8 => "Signature Not Yet Valid",
+        9 => "DNSKEY Missing",
+        10 => "RRSIGs Missing",
+        11 => "No Zone Key Bit Set",
+        12 => "NSEC Missing",
+        13 => "Cached Error",
+        14 => "Not Ready",
+        15 => "Blocked",
+        16 => "Censored",
+        17 => "Filtered",
+        18 => "Prohibited",
+        19 => "Stale NXDOMAIN Answer",
+        20 => "Not Authoritative",
+        21 => "Not Supported",
+        22 => "No Reachable Authority",
+        23 => "Network Error",
+        24 => "Invalid Data",
+        _ => "UNKNOWN",
+    }
+}
+
+//----------- StdoutSink --------------------------------------------------------
+
+/// Writes each report as a `qtype,err_code,qname` line to stdout.
+///
+/// This is the behaviour `erma` had before sinks were pluggable, kept as the
+/// default.
+pub struct StdoutSink;
+
+impl ReportSink for StdoutSink {
+    fn report(
+        &self,
+        qtype: u16,
+        err_code: u16,
+        qname: &RelativeName<Vec<u8>>,
+        _client_addr: SocketAddr,
+    ) {
+        println!("{qtype},{err_code},{qname}");
+    }
+}
+
+//----------- JsonLinesSink -----------------------------------------------------
+
+#[derive(Serialize)]
+struct JsonReportRecord {
+    timestamp: u64,
+    qtype: String,
+    edns_error_code: u16,
+    edns_error_name: &'static str,
+    qname: String,
+    client_addr: SocketAddr,
+}
+
+/// Appends one JSON record per report to a file, one record per line.
+pub struct JsonLinesSink {
+    file: Mutex<std::fs::File>,
+}
+
+impl JsonLinesSink {
+    /// Opens (creating if necessary) `path` for appending.
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl ReportSink for JsonLinesSink {
+    fn report(
+        &self,
+        qtype: u16,
+        err_code: u16,
+        qname: &RelativeName<Vec<u8>>,
+        client_addr: SocketAddr,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let record = JsonReportRecord {
+            timestamp,
+            qtype: Rtype::from(qtype).to_string(),
+            edns_error_code: err_code,
+            edns_error_name: ede_code_name(err_code),
+            qname: qname.to_string(),
+            client_addr,
+        };
+
+        match serde_json::to_string(&record) {
+            Ok(line) => {
+                let mut file = self.file.lock().unwrap();
+                if let Err(err) = writeln!(file, "{line}") {
+                    warn!("Failed to write report to JSON-lines file: {err}");
+                }
+            }
+            Err(err) => warn!("Failed to serialize report: {err}"),
+        }
+    }
+}
+
+//----------- SyslogSink --------------------------------------------------------
+
+/// Forwards each report to the local syslog daemon.
+pub struct SyslogSink {
+    logger: Mutex<syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164>>,
+}
+
+impl SyslogSink {
+    /// Connects to the local syslog daemon (usually over a Unix socket).
+    pub fn connect() -> Result<Self, syslog::Error> {
+        let formatter = syslog::Formatter3164 {
+            facility: syslog::Facility::LOG_DAEMON,
+            hostname: None,
+            process: "erma".into(),
+            pid: std::process::id(),
+        };
+        let logger = syslog::unix(formatter)?;
+        Ok(Self {
+            logger: Mutex::new(logger),
+        })
+    }
+}
+
+impl ReportSink for SyslogSink {
+    fn report(
+        &self,
+        qtype: u16,
+        err_code: u16,
+        qname: &RelativeName<Vec<u8>>,
+        client_addr: SocketAddr,
+    ) {
+        let message = format!(
+            "RFC 9567 report from {client_addr}: qtype={} edns_error={} ({}) qname={qname}",
+            Rtype::from(qtype),
+            err_code,
+            ede_code_name(err_code),
+        );
+        let mut logger = self.logger.lock().unwrap();
+        if let Err(err) = logger.info(message) {
+            warn!("Failed to write report to syslog: {err}");
+        }
+    }
+}
+
+//----------- HttpSink ----------------------------------------------------------
+
+#[derive(Serialize)]
+struct HttpReportRecord {
+    timestamp: u64,
+    qtype: String,
+    edns_error_code: u16,
+    edns_error_name: &'static str,
+    qname: String,
+    client_addr: SocketAddr,
+}
+
+/// Batches reports and POSTs them as a JSON array to a configured HTTP
+/// endpoint, retrying batches that fail in transit or get a server error
+/// (5xx) with backoff; a batch answered with any other non-success status
+/// is dropped.
+///
+/// `report()` only has to enqueue the record onto an unbounded channel; a
+/// background task owns the actual HTTP client, batching and retry logic so
+/// the DNS request path never blocks on the network.
+pub struct HttpSink {
+    tx: tokio::sync::mpsc::UnboundedSender<HttpReportRecord>,
+}
+
+impl HttpSink {
+    /// Spawns the background batching/POSTing task and returns a sink that
+    /// feeds it.
+    pub fn new(endpoint: String) -> Self {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(Self::run(endpoint, rx));
+        Self { tx }
+    }
+
+    async fn run(endpoint: String, mut rx: tokio::sync::mpsc::UnboundedReceiver<HttpReportRecord>) {
+        const BATCH_SIZE: usize = 50;
+        const BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+        const MAX_RETRIES: u32 = 3;
+
+        let client = reqwest::Client::new();
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        loop {
+            let deadline = tokio::time::sleep(BATCH_INTERVAL);
+            tokio::pin!(deadline);
+
+            loop {
+                tokio::select! {
+                    item = rx.recv() => {
+                        match item {
+                            Some(record) => {
+                                batch.push(record);
+                                if batch.len() >= BATCH_SIZE {
+                                    break;
+                                }
+                            }
+                            None => return,
+                        }
+                    }
+                    _ = &mut deadline => break,
+                }
+            }
+
+            if batch.is_empty() {
+                continue;
+            }
+
+            let mut attempt = 0;
+            loop {
+                match client.post(&endpoint).json(&batch).send().await {
+                    Ok(resp) if resp.status().is_success() => break,
+                    Ok(resp) if resp.status().is_server_error() && attempt < MAX_RETRIES => {
+                        attempt += 1;
+                        warn!(
+                            "Report endpoint {endpoint} returned {} (attempt {attempt})",
+                            resp.status()
+                        );
+                        tokio::time::sleep(std::time::Duration::from_secs(1 << attempt)).await;
+                    }
+                    Ok(resp) => {
+                        warn!("Report endpoint {endpoint} returned {}", resp.status());
+                        break;
+                    }
+                    Err(err) if attempt < MAX_RETRIES => {
+                        attempt += 1;
+                        warn!("Failed to POST reports (attempt {attempt}): {err}");
+                        tokio::time::sleep(std::time::Duration::from_secs(1 << attempt)).await;
+                    }
+                    Err(err) => {
+                        warn!("Giving up on batch of {} reports: {err}", batch.len());
+                        break;
+                    }
+                }
+            }
+
+            batch.clear();
+        }
+    }
+}
+
+impl ReportSink for HttpSink {
+    fn report(
+        &self,
+        qtype: u16,
+        err_code: u16,
+        qname: &RelativeName<Vec<u8>>,
+        client_addr: SocketAddr,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let record = HttpReportRecord {
+            timestamp,
+            qtype: Rtype::from(qtype).to_string(),
+            edns_error_code: err_code,
+            edns_error_name: ede_code_name(err_code),
+            qname: qname.to_string(),
+            client_addr,
+        };
+
+        if self.tx.send(record).is_err() {
+            warn!("HTTP report sink's background task has stopped");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Returns a uniquely-named temp file path that doesn't exist yet, so
+    /// `JsonLinesSink::open`'s `create(true)` path is exercised.
+    fn temp_json_lines_path() -> std::path::PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!(
+            "erma-test-reports-{}-{unique}.jsonl",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn json_lines_sink_round_trips_a_report() {
+        let path = temp_json_lines_path();
+        let sink = JsonLinesSink::open(&path).expect("open JSON-lines file");
+
+        let qname = RelativeName::<Vec<u8>>::from_str("www.example.com").unwrap();
+        let client_addr: SocketAddr = "192.0.2.1:53".parse().unwrap();
+        sink.report(Rtype::A.into(), 6, &qname, client_addr);
+
+        let contents = std::fs::read_to_string(&path).expect("read back JSON-lines file");
+        std::fs::remove_file(&path).ok();
+
+        let line = contents.lines().next().expect("one record was written");
+        let record: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+
+        assert_eq!(record["qtype"], "A");
+        assert_eq!(record["edns_error_code"], 6);
+        // EDE code 6 is "DNSSEC Bogus" (RFC 8914); exercises the
+        // code-to-name mapping, not just the raw code.
+        assert_eq!(record["edns_error_name"], "DNSSEC Bogus");
+        assert_eq!(record["qname"], "www.example.com");
+        assert_eq!(record["client_addr"], "192.0.2.1:53");
+    }
+
+    #[test]
+    fn json_lines_sink_appends_rather_than_truncates() {
+        let path = temp_json_lines_path();
+        let qname = RelativeName::<Vec<u8>>::from_str("example.com").unwrap();
+        let client_addr: SocketAddr = "192.0.2.1:53".parse().unwrap();
+
+        {
+            let sink = JsonLinesSink::open(&path).expect("open JSON-lines file");
+            sink.report(Rtype::A.into(), 0, &qname, client_addr);
+        }
+        {
+            let sink = JsonLinesSink::open(&path).expect("reopen JSON-lines file");
+            sink.report(Rtype::AAAA.into(), 0, &qname, client_addr);
+        }
+
+        let contents = std::fs::read_to_string(&path).expect("read back JSON-lines file");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(contents.lines().count(), 2, "both reports must be kept");
+    }
+}
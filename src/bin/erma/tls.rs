@@ -0,0 +1,83 @@
+//! A DNS-over-TLS (RFC 7858) listener built around a [`TcpListener`].
+//!
+//! Wraps a plain TCP listener with a rustls [`TlsAcceptor`] so that the
+//! resulting [`TlsListener`] can be handed to [`StreamServer`] exactly like a
+//! bare [`TcpListener`], running the same middleware chain and
+//! [`AgentService`](crate::agent::AgentService) over an encrypted transport.
+
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use rustls_pemfile::{certs, private_key};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+use domain::net::server::listener::AsyncAccept;
+
+//----------- load_tls_config -------------------------------------------------
+
+/// Loads a certificate chain and private key from PEM files into a rustls
+/// [`ServerConfig`] suitable for [`TlsAcceptor::from`].
+pub fn load_tls_config(cert_path: &Path, key_path: &Path) -> io::Result<Arc<ServerConfig>> {
+    let cert_chain: Vec<CertificateDer<'static>> =
+        certs(&mut BufReader::new(File::open(cert_path)?)).collect::<Result<_, _>>()?;
+
+    let key: PrivateKeyDer<'static> = private_key(&mut BufReader::new(File::open(key_path)?))?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no private key found"))?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, key)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    Ok(Arc::new(config))
+}
+
+//----------- TlsListener ------------------------------------------------------
+
+/// An [`AsyncAccept`] implementation that TLS-wraps every connection
+/// accepted from an inner [`TcpListener`].
+pub struct TlsListener {
+    tcp: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsListener {
+    /// Creates a new TLS listener on top of an already-bound TCP listener.
+    pub fn new(tcp: TcpListener, tls_config: Arc<ServerConfig>) -> Self {
+        Self {
+            tcp,
+            acceptor: TlsAcceptor::from(tls_config),
+        }
+    }
+}
+
+impl AsyncAccept for TlsListener {
+    type Addr = SocketAddr;
+    type Stream = TlsStream<tokio::net::TcpStream>;
+    type Future = Pin<Box<dyn Future<Output = io::Result<(Self::Stream, Self::Addr)>> + Send>>;
+
+    fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<Self::Future>> {
+        let (tcp_stream, addr) = match self.tcp.poll_accept(cx) {
+            Poll::Ready(Ok(accepted)) => accepted,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let acceptor = self.acceptor.clone();
+        let fut = Box::pin(async move {
+            let tls_stream = acceptor.accept(tcp_stream).await?;
+            Ok((tls_stream, addr))
+        });
+
+        Poll::Ready(Ok(fut))
+    }
+}
@@ -0,0 +1,270 @@
+//! An in-memory authoritative zone, loaded from a zonefile at startup.
+//!
+//! This backs the part of `process_request` that answers ordinary
+//! questions about the agent domain (SOA, NS, DNSKEY, and everything else
+//! in the zonefile) rather than RFC 9567 report queries: `erma` needs to be
+//! a complete authoritative server for the domain it reports errors under,
+//! not just a special-case responder for `_er` names.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use domain::base::iana::Rtype;
+use domain::base::{Name, ToName, Ttl};
+use domain::rdata::ZoneRecordData;
+use domain::zonefile::inplace::{Entry, Zonefile};
+
+//----------- ZoneError -----------------------------------------------------
+
+/// An error encountered while loading a zonefile.
+#[derive(Debug)]
+pub enum ZoneError {
+    /// The zonefile could not be read.
+    Io(std::io::Error),
+
+    /// The zonefile was not valid, or one of its records couldn't be
+    /// turned into an owned, in-memory record.
+    Parse(String),
+
+    /// The zonefile has no SOA record at the apex.
+    MissingSoa,
+}
+
+impl std::fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZoneError::Io(err) => write!(f, "I/O error reading zonefile: {err}"),
+            ZoneError::Parse(err) => write!(f, "invalid zonefile: {err}"),
+            ZoneError::MissingSoa => write!(f, "zonefile has no SOA record at the apex"),
+        }
+    }
+}
+
+impl std::error::Error for ZoneError {}
+
+impl From<std::io::Error> for ZoneError {
+    fn from(err: std::io::Error) -> Self {
+        ZoneError::Io(err)
+    }
+}
+
+//----------- ZoneRecord -----------------------------------------------------
+
+/// One resource record loaded from the zonefile. The owning zone's class is
+/// always IN; only that class is supported.
+#[derive(Clone)]
+pub struct ZoneRecord {
+    pub ttl: Ttl,
+    pub data: ZoneRecordData<Vec<u8>, Name<Vec<u8>>>,
+}
+
+//----------- LookupResult ---------------------------------------------------
+
+/// The outcome of looking a QNAME/QTYPE up in a [`Zone`].
+pub enum LookupResult<'a> {
+    /// The owner name exists and has at least one record of the queried
+    /// type (or `QTYPE` is `ANY`/the rrset wasn't type-filtered).
+    Found(Vec<&'a ZoneRecord>),
+
+    /// The owner name exists, but not with the queried type.
+    NoData,
+
+    /// The owner name does not exist in the zone.
+    NxDomain,
+}
+
+//----------- Zone ------------------------------------------------------------
+
+/// A zone loaded wholesale into memory from a zonefile at startup.
+///
+/// Lookups are exact-match only: `erma`'s agent domain is a leaf zone with
+/// no delegations, so wildcard and referral handling aren't needed.
+pub struct Zone {
+    apex: Name<Vec<u8>>,
+    records: HashMap<Name<Vec<u8>>, Vec<ZoneRecord>>,
+}
+
+impl Zone {
+    /// Loads and parses a zonefile rooted at `apex`.
+    pub fn load(path: &Path, apex: Name<Vec<u8>>) -> Result<Self, ZoneError> {
+        let file = File::open(path)?;
+        let mut zonefile = Zonefile::load(&mut BufReader::new(file))
+            .map_err(|err| ZoneError::Parse(err.to_string()))?;
+        zonefile.set_origin(apex.clone());
+
+        let mut records: HashMap<Name<Vec<u8>>, Vec<ZoneRecord>> = HashMap::new();
+        for entry in &mut zonefile {
+            let entry = entry.map_err(|err| ZoneError::Parse(err.to_string()))?;
+            let Entry::Record(record) = entry else {
+                continue;
+            };
+
+            let owner = record
+                .owner()
+                .to_name::<Vec<u8>>()
+                .map_err(|err| ZoneError::Parse(format!("invalid owner name: {err}")))?;
+            let data = record.data().clone().flatten_into();
+
+            records.entry(owner).or_default().push(ZoneRecord {
+                ttl: record.ttl(),
+                data,
+            });
+        }
+
+        let has_soa = records
+            .get(&apex)
+            .map(|rrset| {
+                rrset
+                    .iter()
+                    .any(|r| matches!(r.data, ZoneRecordData::Soa(_)))
+            })
+            .unwrap_or(false);
+        if !has_soa {
+            return Err(ZoneError::MissingSoa);
+        }
+
+        Ok(Self { apex, records })
+    }
+
+    /// The zone's apex (origin) name.
+    pub fn apex(&self) -> &Name<Vec<u8>> {
+        &self.apex
+    }
+
+    /// The SOA record at the apex, guaranteed present by [`Zone::load`].
+    pub fn soa(&self) -> &ZoneRecord {
+        self.records[&self.apex]
+            .iter()
+            .find(|r| matches!(r.data, ZoneRecordData::Soa(_)))
+            .expect("Zone::load verifies a SOA record exists")
+    }
+
+    /// Looks up `qtype` records at `owner`.
+    ///
+    /// Callers are expected to have already checked that `owner` is inside
+    /// this zone (i.e. is or is under `apex()`).
+    pub fn lookup(&self, owner: &Name<Vec<u8>>, qtype: Rtype) -> LookupResult<'_> {
+        let Some(rrset) = self.records.get(owner) else {
+            return LookupResult::NxDomain;
+        };
+
+        let matching: Vec<&ZoneRecord> = rrset
+            .iter()
+            .filter(|r| qtype == Rtype::ANY || r.data.rtype() == qtype)
+            .collect();
+
+        if matching.is_empty() {
+            LookupResult::NoData
+        } else {
+            LookupResult::Found(matching)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Writes `contents` to a uniquely-named temp zonefile and returns its
+    /// path.
+    fn write_test_zonefile(contents: &str) -> std::path::PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!(
+            "erma-test-zone-{}-{unique}.zone",
+            std::process::id()
+        ));
+        let mut file = std::fs::File::create(&path).expect("create temp zonefile");
+        write!(file, "{contents}").unwrap();
+        path
+    }
+
+    const EXAMPLE_ZONE: &str = "\
+@ 3600 IN SOA ns1.example.com. hostmaster.example.com. 1 3600 600 86400 3600
+@ 3600 IN NS ns1.example.com.
+www 3600 IN A 192.0.2.1
+www 3600 IN A 192.0.2.2
+";
+
+    #[test]
+    fn load_succeeds_and_parses_records() {
+        let path = write_test_zonefile(EXAMPLE_ZONE);
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let zone = Zone::load(&path, apex.clone()).expect("zonefile should load");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(zone.apex(), &apex);
+        assert!(matches!(zone.soa().data, ZoneRecordData::Soa(_)));
+    }
+
+    #[test]
+    fn load_rejects_a_zonefile_missing_an_apex_soa() {
+        let path = write_test_zonefile("@ 3600 IN NS ns1.example.com.\n");
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let result = Zone::load(&path, apex);
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(result, Err(ZoneError::MissingSoa)));
+    }
+
+    #[test]
+    fn lookup_returns_found_for_an_existing_owner_and_qtype() {
+        let path = write_test_zonefile(EXAMPLE_ZONE);
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let zone = Zone::load(&path, apex).expect("zonefile should load");
+        std::fs::remove_file(&path).ok();
+
+        let owner = Name::<Vec<u8>>::from_str("www.example.com.").unwrap();
+        match zone.lookup(&owner, Rtype::A) {
+            LookupResult::Found(records) => assert_eq!(records.len(), 2),
+            _ => panic!("expected Found"),
+        }
+    }
+
+    #[test]
+    fn lookup_returns_nodata_for_an_existing_owner_wrong_qtype() {
+        let path = write_test_zonefile(EXAMPLE_ZONE);
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let zone = Zone::load(&path, apex).expect("zonefile should load");
+        std::fs::remove_file(&path).ok();
+
+        let owner = Name::<Vec<u8>>::from_str("www.example.com.").unwrap();
+        assert!(matches!(
+            zone.lookup(&owner, Rtype::AAAA),
+            LookupResult::NoData
+        ));
+    }
+
+    #[test]
+    fn lookup_returns_nxdomain_for_a_nonexistent_owner() {
+        let path = write_test_zonefile(EXAMPLE_ZONE);
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let zone = Zone::load(&path, apex).expect("zonefile should load");
+        std::fs::remove_file(&path).ok();
+
+        let owner = Name::<Vec<u8>>::from_str("nonexistent.example.com.").unwrap();
+        assert!(matches!(
+            zone.lookup(&owner, Rtype::A),
+            LookupResult::NxDomain
+        ));
+    }
+
+    #[test]
+    fn lookup_with_qtype_any_returns_all_records_at_an_owner() {
+        let path = write_test_zonefile(EXAMPLE_ZONE);
+        let apex = Name::<Vec<u8>>::from_str("example.com.").unwrap();
+        let zone = Zone::load(&path, apex.clone()).expect("zonefile should load");
+        std::fs::remove_file(&path).ok();
+
+        match zone.lookup(&apex, Rtype::ANY) {
+            LookupResult::Found(records) => assert_eq!(records.len(), 2),
+            _ => panic!("expected Found"),
+        }
+    }
+}